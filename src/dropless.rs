@@ -0,0 +1,222 @@
+//! A bump allocator for mixed, `Drop`-free types.
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::alloc::Layout;
+use core::cell::RefCell;
+use core::cmp;
+use core::mem::{self, MaybeUninit};
+use core::ptr;
+use core::slice;
+use core::str;
+
+// Initial chunk size in bytes: big enough that most arenas only ever
+// allocate a single chunk, but small enough not to waste much memory on an
+// arena that is never used.
+const INITIAL_SIZE: usize = 4096;
+
+/// An arena that bump-allocates values of arbitrary, possibly mixed, types
+/// out of shared byte chunks.
+///
+/// Unlike [`Arena<T>`](struct.Arena.html), which is monomorphic and owns
+/// (and drops) a single type `T`, a `DroplessArena` can hand out `&mut T`s
+/// for any number of different types `T`, as long as none of them need to
+/// run `Drop`. Since nothing it hands out ever needs dropping, the arena
+/// itself has nothing to do when it goes away beyond freeing its chunks.
+///
+/// ## Example
+///
+/// ```
+/// use typed_arena::DroplessArena;
+///
+/// let arena = DroplessArena::new();
+///
+/// let number = arena.alloc(42u32);
+/// let text = arena.alloc_str("hello");
+/// assert_eq!(*number, 42);
+/// assert_eq!(text, "hello");
+/// ```
+///
+/// Zero-sized layouts (including zero-sized types like `()`, and slices of
+/// them) never touch chunk storage: `alloc_raw` hands back a well-aligned
+/// dangling pointer for them instead, the same one for every zero-sized
+/// allocation of a given alignment.
+pub struct DroplessArena {
+    chunks: RefCell<ChunkList>,
+}
+
+struct Chunk {
+    storage: Box<[MaybeUninit<u8>]>,
+}
+
+impl Chunk {
+    fn new(size: usize) -> Chunk {
+        let mut storage = Vec::with_capacity(size);
+        storage.resize(size, MaybeUninit::uninit());
+        Chunk {
+            storage: storage.into_boxed_slice(),
+        }
+    }
+
+    fn start(&mut self) -> *mut u8 {
+        self.storage.as_mut_ptr() as *mut u8
+    }
+
+    fn end(&mut self) -> *mut u8 {
+        unsafe { self.start().add(self.storage.len()) }
+    }
+}
+
+struct ChunkList {
+    current: Chunk,
+    cursor: *mut u8,
+    end: *mut u8,
+    rest: Vec<Chunk>,
+}
+
+impl ChunkList {
+    #[inline(never)]
+    #[cold]
+    fn grow(&mut self, additional: usize) {
+        let double_cap = self
+            .current
+            .storage
+            .len()
+            .checked_mul(2)
+            .expect("capacity overflow");
+        let required_cap = additional
+            .checked_next_power_of_two()
+            .expect("capacity overflow");
+        let size = cmp::max(cmp::max(double_cap, required_cap), INITIAL_SIZE);
+        let mut chunk = Chunk::new(size);
+        self.cursor = chunk.start();
+        self.end = chunk.end();
+        let old_chunk = mem::replace(&mut self.current, chunk);
+        self.rest.push(old_chunk);
+    }
+}
+
+impl DroplessArena {
+    /// Construct a new, empty `DroplessArena`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::DroplessArena;
+    ///
+    /// let arena = DroplessArena::new();
+    /// # arena.alloc(1u8);
+    /// ```
+    pub fn new() -> DroplessArena {
+        let mut chunk = Chunk::new(INITIAL_SIZE);
+        let cursor = chunk.start();
+        let end = chunk.end();
+        DroplessArena {
+            chunks: RefCell::new(ChunkList {
+                current: chunk,
+                cursor,
+                end,
+                rest: Vec::new(),
+            }),
+        }
+    }
+
+    /// Allocates `layout.size()` bytes aligned to `layout.align()` and
+    /// returns a pointer to the start of them. The memory is uninitialized,
+    /// and must be initialized before it is handed out as a reference by
+    /// callers such as [`alloc`](#method.alloc).
+    ///
+    /// A zero-size `layout` never touches chunk storage; the returned
+    /// pointer is dangling (but non-null and aligned to `layout.align()`),
+    /// matching what `Vec`/`Box` already do for zero-sized types.
+    pub fn alloc_raw(&self, layout: Layout) -> *mut u8 {
+        if layout.size() == 0 {
+            return layout.align() as *mut u8;
+        }
+        loop {
+            let mut chunks = self.chunks.borrow_mut();
+            let cursor = (chunks.cursor as usize)
+                .checked_add(layout.align() - 1)
+                .expect("capacity overflow")
+                & !(layout.align() - 1);
+            let end = chunks.end as usize;
+            if cursor <= end && layout.size() <= end - cursor {
+                chunks.cursor = (cursor + layout.size()) as *mut u8;
+                return cursor as *mut u8;
+            }
+            chunks.grow(layout.size());
+        }
+    }
+
+    /// Allocates a value in the arena, and returns a mutable reference to
+    /// it. `T` must not need dropping: this arena never runs destructors.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::DroplessArena;
+    ///
+    /// let arena = DroplessArena::new();
+    /// let x = arena.alloc(42);
+    /// assert_eq!(*x, 42);
+    /// ```
+    #[inline]
+    pub fn alloc<T>(&self, value: T) -> &mut T {
+        assert!(!mem::needs_drop::<T>());
+        let ptr = self.alloc_raw(Layout::new::<T>()) as *mut T;
+        unsafe {
+            ptr::write(ptr, value);
+            &mut *ptr
+        }
+    }
+
+    /// Copies the bytes of `s` into the arena and returns a `&str` pointing
+    /// at them.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::DroplessArena;
+    ///
+    /// let arena = DroplessArena::new();
+    /// let s = arena.alloc_str("hello world");
+    /// assert_eq!(s, "hello world");
+    /// ```
+    pub fn alloc_str(&self, s: &str) -> &str {
+        let copy = self.alloc_slice_copy(s.as_bytes());
+        unsafe { str::from_utf8_unchecked(copy) }
+    }
+
+    /// Copies the contents of a `Copy` slice into the arena and returns a
+    /// reference to the copy.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::DroplessArena;
+    ///
+    /// let arena = DroplessArena::new();
+    /// let slice = arena.alloc_slice_copy(&[1, 2, 3]);
+    /// assert_eq!(slice, [1, 2, 3]);
+    /// ```
+    pub fn alloc_slice_copy<T: Copy>(&self, slice: &[T]) -> &[T] {
+        if slice.is_empty() {
+            return &[];
+        }
+        let layout = Layout::for_value(slice);
+        let ptr = self.alloc_raw(layout) as *mut T;
+        unsafe {
+            ptr::copy_nonoverlapping(slice.as_ptr(), ptr, slice.len());
+            slice::from_raw_parts(ptr, slice.len())
+        }
+    }
+}
+
+impl Default for DroplessArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -13,6 +13,15 @@
 //! It is slightly less efficient, but simpler internally and uses much less unsafe code.
 //! It is based on a `Vec<Vec<T>>` instead of raw pointers and manual drops.
 //!
+//! For workloads that allocate many different, `Drop`-free types out of one
+//! arena (interning, AST nodes, and the like), see
+//! [`DroplessArena`](struct.DroplessArena.html), which bump-allocates raw
+//! bytes instead of being specialized to a single `T`.
+//!
+//! `Arena<T>` itself is `!Sync`, since it allocates through a `RefCell`. For
+//! sharing one arena across several threads, see
+//! [`SyncArena<T>`](struct.SyncArena.html).
+//!
 //! ## Example
 //!
 //! ```
@@ -50,6 +59,14 @@
 //! a.other.set(Some(b));
 //! b.other.set(Some(a));
 //! ```
+//!
+//! This works as long as `CycleParticipant` has no destructor. If it does
+//! `impl Drop`, dropck conservatively assumes the destructor might read the
+//! `'a` references, and rejects the cycle above. Enabling the nightly-only
+//! `may_dangle` Cargo feature of this crate opts `Arena<T>`'s drop glue into
+//! `#[may_dangle]`, which tells the compiler the arena's destructor never
+//! looks at `T`'s lifetime-tagged fields (it only drops the `T`s in place),
+//! so such self-referential `Drop` types are accepted again.
 
 // Potential optimizations:
 // 1) add and stabilize a method for in-place reallocation of vecs.
@@ -59,6 +76,7 @@
 #![deny(missing_docs)]
 #![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![cfg_attr(not(feature = "std"), feature(alloc))]
+#![cfg_attr(feature = "may_dangle", feature(dropck_eyepatch))]
 
 #[cfg(not(feature = "std"))]
 extern crate alloc;
@@ -73,8 +91,23 @@ use core::cell::RefCell;
 use core::cmp;
 use core::iter;
 use core::mem;
+#[cfg(feature = "may_dangle")]
+use core::mem::ManuallyDrop;
 use core::slice;
 
+mod dropless;
+mod scratch;
+
+pub use dropless::DroplessArena;
+
+use scratch::Scratch;
+
+#[cfg(feature = "std")]
+mod sync;
+
+#[cfg(feature = "std")]
+pub use sync::SyncArena;
+
 #[cfg(test)]
 mod test;
 
@@ -100,12 +133,34 @@ const MIN_CAPACITY: usize = 1;
 /// assert!(vegeta.level > 9000);
 /// ```
 pub struct Arena<T> {
-    chunks: RefCell<ChunkList<T>>,
+    chunks: Chunks<T>,
 }
 
-struct ChunkList<T> {
-    current: Vec<T>,
-    rest: Vec<Vec<T>>,
+// Plain `Arena<T>` has no `Drop` impl of its own (its fields' implicit drop
+// glue is enough), which keeps dropck from conservatively restricting what
+// `T` may borrow, as in the "Safe Cycles" example above.
+//
+// With the `may_dangle` feature, `Arena<T>` gets an explicit
+// `#[may_dangle]` `Drop` impl instead (see below), which needs `chunks`
+// wrapped in `ManuallyDrop` so it can still drop it itself *and* so
+// `into_vec` can still move it out of `self` despite that `Drop` impl.
+#[cfg(not(feature = "may_dangle"))]
+type Chunks<T> = RefCell<ChunkList<T>>;
+#[cfg(feature = "may_dangle")]
+type Chunks<T> = ManuallyDrop<RefCell<ChunkList<T>>>;
+
+#[cfg(not(feature = "may_dangle"))]
+fn new_chunks<T>(list: ChunkList<T>) -> Chunks<T> {
+    RefCell::new(list)
+}
+#[cfg(feature = "may_dangle")]
+fn new_chunks<T>(list: ChunkList<T>) -> Chunks<T> {
+    ManuallyDrop::new(RefCell::new(list))
+}
+
+pub(crate) struct ChunkList<T> {
+    pub(crate) current: Vec<T>,
+    pub(crate) rest: Vec<Vec<T>>,
 }
 
 impl<T> Arena<T> {
@@ -137,7 +192,7 @@ impl<T> Arena<T> {
     pub fn with_capacity(n: usize) -> Arena<T> {
         let n = cmp::max(MIN_CAPACITY, n);
         Arena {
-            chunks: RefCell::new(ChunkList {
+            chunks: new_chunks(ChunkList {
                 current: Vec::with_capacity(n),
                 rest: Vec::new(),
             }),
@@ -247,6 +302,60 @@ impl<T> Arena<T> {
         new_slice_ref
     }
 
+    /// Uses the contents of an iterator to allocate values in the arena.
+    /// Returns a mutable slice that contains these values.
+    ///
+    /// Unlike [`alloc_extend`](#method.alloc_extend), this works well with
+    /// iterators that don't report an accurate [`size_hint`], such as
+    /// `filter`: it first drains the iterator into a small scratch buffer
+    /// to learn how many items there are, then moves them all into the
+    /// current chunk if they fit there, or else into a fresh chunk sized
+    /// exactly for them. The returned slice is therefore always
+    /// a single contiguous run, never stitched together out of the tail of
+    /// one chunk and the head of the next.
+    ///
+    /// [`size_hint`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.size_hint
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let arena = Arena::new();
+    /// let abc = arena.alloc_from_iter("abcdefg".chars().filter(|c| *c < 'd'));
+    /// assert_eq!(abc, ['a', 'b', 'c']);
+    /// ```
+    pub fn alloc_from_iter<I>(&self, iterable: I) -> &mut [T]
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut scratch = Scratch::new();
+        for item in iterable {
+            scratch.push(item);
+        }
+        let len = scratch.len();
+        if len == 0 {
+            return &mut [];
+        }
+
+        let mut chunks = self.chunks.borrow_mut();
+        if chunks.current.len() + len > chunks.current.capacity() {
+            chunks.reserve_exact(len);
+        }
+        let start = chunks.current.len();
+        unsafe {
+            let dest = chunks.current.as_mut_ptr().add(start);
+            scratch.move_into(dest);
+            chunks.current.set_len(start + len);
+        }
+
+        let new_slice_ref = &mut chunks.current[start..];
+        // Extend the lifetime from that of `chunks_borrow` to that of `self`.
+        // See the comment in `alloc_extend` above: this is sound for the
+        // same reason.
+        unsafe { mem::transmute::<&mut [T], &mut [T]>(new_slice_ref) }
+    }
+
     /// Allocates space for a given number of values, but doesn't initialize it.
     ///
     /// ## Unsafety and Undefined Behavior
@@ -294,6 +403,20 @@ impl<T> Arena<T> {
         unsafe { slice::from_raw_parts_mut(slice.as_ptr() as *mut T, len) as *mut _ }
     }
 
+    #[cfg(not(feature = "may_dangle"))]
+    fn into_chunks(self) -> ChunkList<T> {
+        self.chunks.into_inner()
+    }
+
+    #[cfg(feature = "may_dangle")]
+    fn into_chunks(self) -> ChunkList<T> {
+        let mut arena = ManuallyDrop::new(self);
+        // SAFETY: wrapping `self` in `ManuallyDrop` keeps its `Drop` impl
+        // from running (which would otherwise drop `chunks` out from under
+        // us), and we only ever take `chunks` out once here.
+        unsafe { ManuallyDrop::take(&mut arena.chunks) }.into_inner()
+    }
+
     /// Convert this `Arena` into a `Vec<T>`.
     ///
     /// Items in the resulting `Vec<T>` appear in the order that they were
@@ -315,7 +438,7 @@ impl<T> Arena<T> {
     /// assert_eq!(easy_as_123, vec!["a", "b", "c"]);
     /// ```
     pub fn into_vec(self) -> Vec<T> {
-        let mut chunks = self.chunks.into_inner();
+        let mut chunks = self.into_chunks();
         // keep order of allocation in the resulting Vec
         let n = chunks
             .rest
@@ -384,6 +507,49 @@ impl<T> Arena<T> {
             },
         }
     }
+
+    /// Drops all values currently in the arena, but retains the largest
+    /// chunk of backing memory so that a following round of `alloc`s can
+    /// reuse it instead of paying for a fresh allocation. All other chunks
+    /// are freed.
+    ///
+    /// Because this takes `&mut self`, there cannot be any outstanding
+    /// references into the arena, so resetting chunk lengths back to zero
+    /// is safe.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    ///
+    /// arena.alloc("a");
+    /// arena.alloc("b");
+    ///
+    /// arena.clear();
+    ///
+    /// assert!(arena.into_vec().is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        let chunks = self.chunks.get_mut();
+
+        // Gather every chunk (the current one and all the `rest`) so we can
+        // pick the largest one to keep around as the new `current`; the
+        // others are dropped here, releasing their memory.
+        let mut all_chunks = mem::take(&mut chunks.rest);
+        all_chunks.push(mem::take(&mut chunks.current));
+
+        let largest_index = all_chunks
+            .iter()
+            .enumerate()
+            .max_by_key(|&(_, chunk)| chunk.capacity())
+            .map(|(index, _)| index)
+            .unwrap();
+        let mut largest = all_chunks.swap_remove(largest_index);
+        largest.clear();
+        chunks.current = largest;
+    }
 }
 
 impl<T> Default for Arena<T> {
@@ -392,10 +558,28 @@ impl<T> Default for Arena<T> {
     }
 }
 
+// Without this, dropck infers that `Arena<T>` may access `T`'s lifetime
+// parameters while running `T`'s destructor, so it refuses to let `T`
+// contain references with that same lifetime (as in the "Safe Cycles"
+// example above) once `T: Drop`. `#[may_dangle]` tells dropck the opposite:
+// dropping an `Arena<T>` only ever drops `T` values in place, and never
+// otherwise reads or writes through them, so those references are still
+// allowed to dangle by the time this runs.
+//
+// `chunks` is `ManuallyDrop` under this feature (see `Chunks<T>` above) so
+// that `into_vec` can still move it out of `self` despite `Arena<T>` now
+// implementing `Drop`; that also means we have to drop it ourselves here.
+#[cfg(feature = "may_dangle")]
+unsafe impl<#[may_dangle] T> Drop for Arena<T> {
+    fn drop(&mut self) {
+        unsafe { ManuallyDrop::drop(&mut self.chunks) }
+    }
+}
+
 impl<T> ChunkList<T> {
     #[inline(never)]
     #[cold]
-    fn reserve(&mut self, additional: usize) {
+    pub(crate) fn reserve(&mut self, additional: usize) {
         let double_cap = self
             .current
             .capacity()
@@ -408,9 +592,20 @@ impl<T> ChunkList<T> {
         let chunk = mem::replace(&mut self.current, Vec::with_capacity(new_capacity));
         self.rest.push(chunk);
     }
+
+    /// Like `reserve`, but the new chunk has capacity for exactly
+    /// `additional` items rather than a doubled capacity. Used where the
+    /// caller already knows precisely how many items it's about to push
+    /// and doesn't want to over-allocate.
+    #[inline(never)]
+    #[cold]
+    pub(crate) fn reserve_exact(&mut self, additional: usize) {
+        let chunk = mem::replace(&mut self.current, Vec::with_capacity(additional));
+        self.rest.push(chunk);
+    }
 }
 
-enum ChunkListPosition {
+pub(crate) enum ChunkListPosition {
     Rest { index: usize, inner_index: usize },
     Current { index: usize },
 }
@@ -419,8 +614,8 @@ enum ChunkListPosition {
 ///
 /// This struct is created by the [`iter_mut`](struct.Arena.html#method.iter_mut) method on [Arenas](struct.Arena.html).
 pub struct IterMut<'a, T: 'a> {
-    chunks: &'a mut ChunkList<T>,
-    position: ChunkListPosition,
+    pub(crate) chunks: &'a mut ChunkList<T>,
+    pub(crate) position: ChunkListPosition,
 }
 
 impl<'a, T> Iterator for IterMut<'a, T> {
@@ -0,0 +1,85 @@
+//! A small growable buffer used to drain an iterator of unknown length
+//! before committing it to arena storage. See
+//! [`Arena::alloc_from_iter`](struct.Arena.html#method.alloc_from_iter).
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::mem::{self, MaybeUninit};
+use core::ptr;
+
+// Small iterators (the common case) fit inline, with no heap allocation at
+// all; only an iterator that overruns this falls back to a `Vec`.
+const INLINE_CAPACITY: usize = 8;
+
+pub(crate) struct Scratch<T> {
+    inline: [MaybeUninit<T>; INLINE_CAPACITY],
+    inline_len: usize,
+    spilled: Vec<T>,
+}
+
+impl<T> Scratch<T> {
+    pub(crate) fn new() -> Self {
+        Scratch {
+            // An uninitialized array of `MaybeUninit<T>` needs no
+            // initialization of its own.
+            inline: unsafe { MaybeUninit::uninit().assume_init() },
+            inline_len: 0,
+            spilled: Vec::new(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, value: T) {
+        if self.spilled.is_empty() && self.inline_len < INLINE_CAPACITY {
+            self.inline[self.inline_len] = MaybeUninit::new(value);
+            self.inline_len += 1;
+        } else {
+            if self.spilled.is_empty() {
+                self.spilled.reserve(INLINE_CAPACITY * 2);
+                for slot in &mut self.inline[..self.inline_len] {
+                    let slot = mem::replace(slot, MaybeUninit::uninit());
+                    self.spilled.push(unsafe { slot.assume_init() });
+                }
+                self.inline_len = 0;
+            }
+            self.spilled.push(value);
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        if self.spilled.is_empty() {
+            self.inline_len
+        } else {
+            self.spilled.len()
+        }
+    }
+
+    /// Moves every collected value, in order, into the `len()` slots
+    /// starting at `dest`, which must be valid for writes of that many
+    /// `T`s. Consumes `self` without running `T`'s destructor on the
+    /// values, since ownership has moved to `dest`.
+    pub(crate) unsafe fn move_into(mut self, dest: *mut T) {
+        if self.spilled.is_empty() {
+            for (i, slot) in self.inline[..self.inline_len].iter_mut().enumerate() {
+                let slot = mem::replace(slot, MaybeUninit::uninit());
+                ptr::write(dest.add(i), slot.assume_init());
+            }
+            self.inline_len = 0;
+        } else {
+            ptr::copy_nonoverlapping(self.spilled.as_ptr(), dest, self.spilled.len());
+            // The values now belong to `dest`; forget about them here
+            // without dropping them.
+            self.spilled.set_len(0);
+        }
+    }
+}
+
+impl<T> Drop for Scratch<T> {
+    fn drop(&mut self) {
+        if self.spilled.is_empty() {
+            for slot in &mut self.inline[..self.inline_len] {
+                unsafe { ptr::drop_in_place(slot.as_mut_ptr()) };
+            }
+        }
+    }
+}
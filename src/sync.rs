@@ -0,0 +1,174 @@
+//! A `Sync` arena that can be allocated into concurrently through `&self`.
+
+use std::sync::Mutex;
+
+use core::cmp;
+use core::mem;
+
+use super::{ChunkList, ChunkListPosition, IterMut};
+
+// Initial size in bytes.
+const INITIAL_SIZE: usize = 1024;
+// Minimum capacity. Must be larger than 0.
+const MIN_CAPACITY: usize = 1;
+
+/// A `Sync` arena of objects of type `T`.
+///
+/// This is the same data structure as [`Arena<T>`](struct.Arena.html), but
+/// the chunk list is behind a `Mutex` instead of a `RefCell`, so many
+/// threads can call [`alloc`](#method.alloc) through a shared `&SyncArena<T>`
+/// at once. Because two threads could otherwise race to hand out overlapping
+/// `&mut T`s into the same backing storage, `alloc` only ever hands out
+/// shared `&T` references. Methods that need unique access to the arena's
+/// contents, like [`iter_mut`](#method.iter_mut), still take `&mut self`.
+///
+/// This mirrors how rustc wraps its typed arenas in a lock to share them
+/// across a parallel compilation session.
+///
+/// ## Example
+///
+/// ```
+/// use typed_arena::SyncArena;
+///
+/// struct Monster {
+///     level: u32,
+/// }
+///
+/// let monsters = SyncArena::new();
+///
+/// let vegeta = monsters.alloc(Monster { level: 9001 });
+/// assert!(vegeta.level > 9000);
+/// ```
+pub struct SyncArena<T> {
+    chunks: Mutex<ChunkList<T>>,
+}
+
+impl<T> SyncArena<T> {
+    /// Construct a new arena.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::SyncArena;
+    ///
+    /// let arena = SyncArena::new();
+    /// # arena.alloc(1);
+    /// ```
+    pub fn new() -> SyncArena<T> {
+        let size = cmp::max(1, mem::size_of::<T>());
+        SyncArena::with_capacity(INITIAL_SIZE / size)
+    }
+
+    /// Construct a new arena with capacity for `n` values pre-allocated.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::SyncArena;
+    ///
+    /// let arena = SyncArena::with_capacity(1337);
+    /// # arena.alloc(1);
+    /// ```
+    pub fn with_capacity(n: usize) -> SyncArena<T> {
+        let n = cmp::max(MIN_CAPACITY, n);
+        SyncArena {
+            chunks: Mutex::new(ChunkList {
+                current: Vec::with_capacity(n),
+                rest: Vec::new(),
+            }),
+        }
+    }
+
+    /// Allocates a value in the arena, and returns a shared reference to
+    /// that value.
+    ///
+    /// Unlike [`Arena::alloc`](struct.Arena.html#method.alloc), this takes
+    /// `&self` and can be called from multiple threads at once, which is
+    /// why it returns `&T` rather than `&mut T`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::SyncArena;
+    ///
+    /// let arena = SyncArena::new();
+    /// let x = arena.alloc(42);
+    /// assert_eq!(*x, 42);
+    /// ```
+    #[inline]
+    pub fn alloc(&self, value: T) -> &T {
+        let mut chunks = self.chunks.lock().unwrap();
+        let len = chunks.current.len();
+        if len == chunks.current.capacity() {
+            chunks.reserve(1);
+        }
+        chunks.current.push(value);
+        // Avoid going through `Vec::deref`, which overlaps other references
+        // we have already handed out!
+        //
+        // This is sound because `ChunkList` never moves an already-pushed
+        // value: `reserve` only ever replaces `current` with a fresh,
+        // empty `Vec` and files the old one away in `rest`, so the address
+        // we just computed remains valid for as long as the arena lives.
+        let len = chunks.current.len();
+        unsafe { &*chunks.current.as_ptr().add(len - 1) }
+    }
+
+    /// Convert this arena into a `Vec<T>`.
+    ///
+    /// Items in the resulting `Vec<T>` appear in the order that they were
+    /// allocated in.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use typed_arena::SyncArena;
+    ///
+    /// let arena = SyncArena::new();
+    ///
+    /// arena.alloc("a");
+    /// arena.alloc("b");
+    /// arena.alloc("c");
+    ///
+    /// let easy_as_123 = arena.into_vec();
+    ///
+    /// assert_eq!(easy_as_123, vec!["a", "b", "c"]);
+    /// ```
+    pub fn into_vec(self) -> Vec<T> {
+        let mut chunks = self.chunks.into_inner().unwrap();
+        // keep order of allocation in the resulting Vec
+        let n = chunks
+            .rest
+            .iter()
+            .fold(chunks.current.len(), |a, v| a + v.len());
+        let mut result = Vec::with_capacity(n);
+        for mut vec in chunks.rest {
+            result.append(&mut vec);
+        }
+        result.append(&mut chunks.current);
+        result
+    }
+
+    /// Returns an iterator that allows modifying each value.
+    ///
+    /// Items are yielded in the order that they were allocated.
+    ///
+    /// Like [`Arena::iter_mut`](struct.Arena.html#method.iter_mut), this
+    /// requires unique access to the arena, so it takes `&mut self` rather
+    /// than going through the lock.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            chunks: self.chunks.get_mut().unwrap(),
+            position: ChunkListPosition::Rest {
+                index: 0,
+                inner_index: 0,
+            },
+        }
+    }
+}
+
+impl<T> Default for SyncArena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,270 @@
+use super::{Arena, DroplessArena};
+#[cfg(feature = "std")]
+use super::SyncArena;
+
+#[test]
+fn dropless_alloc_basic() {
+    let arena = DroplessArena::new();
+
+    let a = arena.alloc(1u32);
+    let b = arena.alloc(2u64);
+    assert_eq!(*a, 1);
+    assert_eq!(*b, 2);
+
+    assert_eq!(arena.alloc_str("hello"), "hello");
+    assert_eq!(arena.alloc_slice_copy(&[1, 2, 3]), [1, 2, 3]);
+    assert_eq!(arena.alloc_slice_copy::<u8>(&[]), &[] as &[u8]);
+}
+
+#[test]
+fn dropless_zero_sized() {
+    let arena = DroplessArena::new();
+
+    // Allocating a ZST must not panic, and must not touch chunk storage.
+    let unit = arena.alloc(());
+    assert_eq!(*unit, ());
+
+    let units = arena.alloc_slice_copy(&[(), (), ()]);
+    assert_eq!(units.len(), 3);
+
+    // A normal, non-zero-sized allocation still works right after.
+    let n = arena.alloc(42u32);
+    assert_eq!(*n, 42);
+}
+
+#[test]
+fn dropless_alignment() {
+    #[derive(Clone, Copy)]
+    #[repr(align(64))]
+    struct Aligned64(u8);
+
+    let arena = DroplessArena::new();
+
+    // Force several chunk growths while repeatedly allocating a
+    // type with an alignment requirement stricter than a byte, to make
+    // sure `alloc_raw` rounds the cursor up correctly in every chunk.
+    for i in 0..2000u32 {
+        let value = arena.alloc(Aligned64(i as u8));
+        assert_eq!(value as *const Aligned64 as usize % 64, 0);
+        assert_eq!(value.0, i as u8);
+    }
+}
+
+#[test]
+fn dropless_values_survive_chunk_growth() {
+    let arena = DroplessArena::new();
+
+    // Allocate enough `i32`s that the arena must grow past its initial
+    // chunk, then check that references handed out before the growth are
+    // still valid and unmodified afterwards.
+    let refs: Vec<&mut i32> = (0..10_000).map(|i| arena.alloc(i)).collect();
+    for (i, r) in refs.iter().enumerate() {
+        assert_eq!(**r, i as i32);
+    }
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn sync_arena_alloc_and_into_vec() {
+    let mut arena = SyncArena::new();
+    arena.alloc(1);
+    arena.alloc(2);
+    arena.alloc(3);
+
+    for x in arena.iter_mut() {
+        *x *= 10;
+    }
+
+    assert_eq!(arena.into_vec(), vec![10, 20, 30]);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn sync_arena_concurrent_alloc() {
+    use std::sync::Arc;
+    use std::thread;
+
+    const THREADS: usize = 8;
+    const PER_THREAD: usize = 500;
+
+    let arena = Arc::new(SyncArena::with_capacity(1));
+    let handles: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let arena = Arc::clone(&arena);
+            thread::spawn(move || {
+                // Every thread allocates through the same shared `&SyncArena`;
+                // the returned `&i32`s must all stay valid and distinct even
+                // as other threads trigger chunk growth concurrently.
+                let refs: Vec<&i32> = (0..PER_THREAD)
+                    .map(|i| arena.alloc((t * PER_THREAD + i) as i32))
+                    .collect();
+                for (i, r) in refs.iter().enumerate() {
+                    assert_eq!(**r, (t * PER_THREAD + i) as i32);
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let arena = Arc::try_unwrap(arena).unwrap_or_else(|_| panic!("threads not joined"));
+    let mut values = arena.into_vec();
+    values.sort_unstable();
+    let expected: Vec<i32> = (0..(THREADS * PER_THREAD) as i32).collect();
+    assert_eq!(values, expected);
+}
+
+#[test]
+fn clear_drops_values_and_empties_arena() {
+    use std::cell::Cell;
+
+    struct CountDrops<'a>(&'a Cell<usize>);
+    impl<'a> Drop for CountDrops<'a> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    let drops = Cell::new(0);
+    let mut arena = Arena::new();
+    for _ in 0..10 {
+        arena.alloc(CountDrops(&drops));
+    }
+
+    arena.clear();
+    assert_eq!(drops.get(), 10);
+    assert!(arena.into_vec().is_empty());
+}
+
+#[test]
+fn clear_reuses_the_largest_chunk() {
+    let mut arena = Arena::with_capacity(1);
+
+    // Force the arena to grow through several chunks of increasing
+    // capacity, so the last chunk allocated is also the largest.
+    for i in 0..10_000 {
+        arena.alloc(i);
+    }
+    let largest_capacity = arena.chunks.borrow().current.capacity();
+
+    arena.clear();
+
+    // `clear` should have kept that largest chunk as the new `current`
+    // (with its length reset to zero) instead of discarding it along with
+    // the rest.
+    let chunks = arena.chunks.borrow();
+    assert_eq!(chunks.current.len(), 0);
+    assert_eq!(chunks.current.capacity(), largest_capacity);
+    assert!(chunks.rest.is_empty());
+    drop(chunks);
+
+    // And the arena is still fully usable afterwards.
+    arena.alloc(42);
+    assert_eq!(arena.into_vec(), vec![42]);
+}
+
+#[test]
+fn arena_of_drop_type_runs_destructors_exactly_once() {
+    use std::cell::Cell;
+
+    struct CountDrops<'a>(&'a Cell<usize>);
+    impl<'a> Drop for CountDrops<'a> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    let drops = Cell::new(0);
+    {
+        let arena = Arena::new();
+        for _ in 0..10 {
+            arena.alloc(CountDrops(&drops));
+        }
+        // dropped here, with or without the `may_dangle` feature
+    }
+    assert_eq!(drops.get(), 10);
+
+    // `into_vec` must not itself drop anything out from under the
+    // returned `Vec` (it moves the `T`s into that `Vec` without dropping any).
+    let drops2 = Cell::new(0);
+    let arena = Arena::new();
+    arena.alloc(CountDrops(&drops2));
+    arena.alloc(CountDrops(&drops2));
+    let v = arena.into_vec();
+    assert_eq!(drops2.get(), 0);
+    assert_eq!(v.len(), 2);
+    drop(v);
+    assert_eq!(drops2.get(), 2);
+}
+
+// Only meaningful on nightly with `--features may_dangle`; exercises the
+// exact self-referential-`Drop`-cycle case that feature exists for.
+#[cfg(feature = "may_dangle")]
+#[test]
+fn may_dangle_allows_self_referential_drop_cycle() {
+    use std::cell::Cell;
+
+    struct Node<'a> {
+        other: Cell<Option<&'a Node<'a>>>,
+    }
+    impl<'a> Drop for Node<'a> {
+        fn drop(&mut self) {}
+    }
+
+    let arena = Arena::new();
+    let a = arena.alloc(Node {
+        other: Cell::new(None),
+    });
+    let b = arena.alloc(Node {
+        other: Cell::new(None),
+    });
+    a.other.set(Some(b));
+    b.other.set(Some(a));
+}
+
+#[test]
+fn alloc_from_iter_around_inline_capacity() {
+    // `Scratch`'s inline buffer holds 8 items before it spills to a `Vec`;
+    // exercise one item below, at, and above that boundary.
+    for len in [0, 1, 7, 8, 9, 20] {
+        let arena = Arena::new();
+        let slice = arena.alloc_from_iter(0..len);
+        assert_eq!(slice.len(), len);
+        for (i, &value) in slice.iter().enumerate() {
+            assert_eq!(value, i);
+        }
+    }
+}
+
+#[test]
+fn alloc_from_iter_with_unknown_size_hint() {
+    // `filter` reports `(0, Some(upper))`, so this can't rely on the
+    // iterator's size hint to know how much space to reserve up front.
+    let arena = Arena::new();
+    let evens = arena.alloc_from_iter((0..20).filter(|i| i % 2 == 0));
+    assert_eq!(evens, (0..20).filter(|i| i % 2 == 0).collect::<Vec<_>>());
+}
+
+#[test]
+fn alloc_from_iter_spans_chunk_boundary() {
+    // Fill the current chunk almost to capacity first, so a later
+    // `alloc_from_iter` call must grow into a fresh, larger chunk to fit
+    // the whole batch contiguously.
+    let arena = Arena::with_capacity(4);
+    arena.alloc(-1);
+    arena.alloc(-2);
+    arena.alloc(-3);
+
+    let batch = arena.alloc_from_iter(0..10);
+    assert_eq!(batch.len(), 10);
+    for (i, &value) in batch.iter().enumerate() {
+        assert_eq!(value, i as i32);
+    }
+
+    assert_eq!(
+        arena.into_vec(),
+        vec![-1, -2, -3, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9]
+    );
+}